@@ -0,0 +1,277 @@
+use crate::money::Money;
+use crate::{Account, ClientId, TxId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Lifecycle of a transaction that can be disputed.
+///
+/// A freshly ingested deposit or withdrawal starts out `Processed`. From
+/// there the only legal transitions are `Processed -> Disputed`,
+/// `Disputed -> Resolved` and `Disputed -> ChargedBack`; anything else
+/// (e.g. disputing a `Resolved` tx, or a chargeback on a non-disputed
+/// one) is rejected by the `Ledger`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxState {
+    Processed,
+    Disputed,
+    Resolved,
+    ChargedBack,
+}
+
+/// Whether a disputable transaction was a deposit or a withdrawal. Only
+/// deposits can be disputed: disputing a withdrawal (or a non-existent
+/// amount) would otherwise let `held` grow without a matching movement
+/// out of `available`, breaking the `available + held == total` invariant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TxKind {
+    Deposit,
+    Withdrawal,
+}
+
+/// A transaction could not be applied to the ledger. Every ingestion and
+/// dispute-lifecycle method returns one of these instead of silently
+/// dropping the record, so callers can tell "no effect because invalid"
+/// apart from "successfully applied".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerError {
+    NotEnoughFunds,
+    UnknownTx,
+    AlreadyDisputed,
+    NotDisputed,
+    FrozenAccount,
+    MissingAmount,
+    NotADeposit,
+    AmountOverflow,
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            LedgerError::NotEnoughFunds => "not enough available funds",
+            LedgerError::UnknownTx => "unknown transaction id",
+            LedgerError::AlreadyDisputed => "transaction is already disputed or resolved",
+            LedgerError::NotDisputed => "transaction is not under dispute",
+            LedgerError::FrozenAccount => "account is locked",
+            LedgerError::MissingAmount => "deposit/withdrawal is missing an amount",
+            LedgerError::NotADeposit => "only a deposit can be disputed",
+            LedgerError::AmountOverflow => "resulting balance would overflow",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+/// Maintains `available`/`held`/`locked` per client incrementally as each
+/// record is ingested, instead of buffering every transaction forever.
+/// Only the data a dispute can act on is kept: the amount, kind and
+/// current state of each deposit or withdrawal `(client, tx)`, not the
+/// full transaction record. These entries are never removed, so memory
+/// grows by one compact entry per deposit or withdrawal. `total` is
+/// always `available + held` (see `Account::total`): dispute and resolve
+/// move `amount` between the two fields and a chargeback removes it from
+/// `held`, rather than recomputing either from scratch.
+#[derive(Debug, Default)]
+pub struct Ledger {
+    accounts: HashMap<ClientId, Account>,
+    transaction_amounts: HashMap<(ClientId, TxId), Money>,
+    transaction_kinds: HashMap<(ClientId, TxId), TxKind>,
+    transaction_state: HashMap<(ClientId, TxId), TxState>,
+}
+
+impl Ledger {
+    fn account_mut(&mut self, client: ClientId) -> &mut Account {
+        self.accounts.entry(client).or_default()
+    }
+
+    pub fn deposit(
+        &mut self,
+        client: ClientId,
+        tx: TxId,
+        amount: Option<Money>,
+    ) -> Result<(), LedgerError> {
+        let amount = amount.ok_or(LedgerError::MissingAmount)?;
+        let account = self.account_mut(client);
+        if account.locked {
+            return Err(LedgerError::FrozenAccount);
+        }
+        account.available = account
+            .available
+            .checked_add(amount)
+            .ok_or(LedgerError::AmountOverflow)?;
+        self.record(client, tx, TxKind::Deposit, amount);
+        Ok(())
+    }
+
+    pub fn withdraw(
+        &mut self,
+        client: ClientId,
+        tx: TxId,
+        amount: Option<Money>,
+    ) -> Result<(), LedgerError> {
+        let amount = amount.ok_or(LedgerError::MissingAmount)?;
+        let account = self.account_mut(client);
+        if account.locked {
+            return Err(LedgerError::FrozenAccount);
+        }
+        if account.available < amount {
+            return Err(LedgerError::NotEnoughFunds);
+        }
+        account.available = account
+            .available
+            .checked_sub(amount)
+            .ok_or(LedgerError::AmountOverflow)?;
+        self.record(client, tx, TxKind::Withdrawal, amount);
+        Ok(())
+    }
+
+    fn record(&mut self, client: ClientId, tx: TxId, kind: TxKind, amount: Money) {
+        let key = (client, tx);
+        self.transaction_amounts.insert(key, amount);
+        self.transaction_kinds.insert(key, kind);
+        self.transaction_state.insert(key, TxState::Processed);
+    }
+
+    pub fn dispute(&mut self, client: ClientId, tx: TxId) -> Result<(), LedgerError> {
+        let key = (client, tx);
+        let state = *self.transaction_state.get(&key).ok_or(LedgerError::UnknownTx)?;
+        if state != TxState::Processed {
+            return Err(LedgerError::AlreadyDisputed);
+        }
+        if self.transaction_kinds[&key] != TxKind::Deposit {
+            return Err(LedgerError::NotADeposit);
+        }
+        if self.accounts.get(&client).is_some_and(|a| a.locked) {
+            return Err(LedgerError::FrozenAccount);
+        }
+        let amount = self.transaction_amounts[&key];
+        let account = self.account_mut(client);
+        let available = account
+            .available
+            .checked_sub(amount)
+            .ok_or(LedgerError::AmountOverflow)?;
+        let held = account
+            .held
+            .checked_add(amount)
+            .ok_or(LedgerError::AmountOverflow)?;
+        account.available = available;
+        account.held = held;
+        self.transaction_state.insert(key, TxState::Disputed);
+        Ok(())
+    }
+
+    pub fn resolve(&mut self, client: ClientId, tx: TxId) -> Result<(), LedgerError> {
+        let key = (client, tx);
+        let state = *self.transaction_state.get(&key).ok_or(LedgerError::UnknownTx)?;
+        if state != TxState::Disputed {
+            return Err(LedgerError::NotDisputed);
+        }
+        if self.accounts.get(&client).is_some_and(|a| a.locked) {
+            return Err(LedgerError::FrozenAccount);
+        }
+        let amount = self.transaction_amounts[&key];
+        let account = self.account_mut(client);
+        debug_assert!(account.held >= amount, "held funds went negative");
+        let held = account
+            .held
+            .checked_sub(amount)
+            .ok_or(LedgerError::AmountOverflow)?;
+        let available = account
+            .available
+            .checked_add(amount)
+            .ok_or(LedgerError::AmountOverflow)?;
+        account.held = held;
+        account.available = available;
+        self.transaction_state.insert(key, TxState::Resolved);
+        Ok(())
+    }
+
+    pub fn chargeback(&mut self, client: ClientId, tx: TxId) -> Result<(), LedgerError> {
+        let key = (client, tx);
+        let state = *self.transaction_state.get(&key).ok_or(LedgerError::UnknownTx)?;
+        if state != TxState::Disputed {
+            return Err(LedgerError::NotDisputed);
+        }
+        if self.accounts.get(&client).is_some_and(|a| a.locked) {
+            return Err(LedgerError::FrozenAccount);
+        }
+        let amount = self.transaction_amounts[&key];
+        let account = self.account_mut(client);
+        debug_assert!(account.held >= amount, "held funds went negative");
+        let held = account
+            .held
+            .checked_sub(amount)
+            .ok_or(LedgerError::AmountOverflow)?;
+        account.held = held;
+        account.locked = true;
+        self.transaction_state.insert(key, TxState::ChargedBack);
+        Ok(())
+    }
+
+    /// All accounts, in ascending client id order.
+    pub fn accounts(&self) -> impl Iterator<Item = (&ClientId, &Account)> {
+        let mut accounts: Vec<_> = self.accounts.iter().collect();
+        accounts.sort_by_key(|(id, _)| **id);
+        accounts.into_iter()
+    }
+
+    pub fn account(&self, client: ClientId) -> Option<&Account> {
+        self.accounts.get(&client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(s: &str) -> Money {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn disputing_a_withdrawal_is_rejected() {
+        let mut ledger = Ledger::default();
+        ledger.deposit(1, 1, Some(money("2.0"))).unwrap();
+        ledger.withdraw(1, 2, Some(money("1.0"))).unwrap();
+        assert_eq!(ledger.dispute(1, 2), Err(LedgerError::NotADeposit));
+        let account = ledger.account(1).copied().unwrap();
+        assert_eq!(account.available, money("1.0"));
+        assert_eq!(account.held, money("0.0"));
+    }
+
+    #[test]
+    fn disputing_the_same_deposit_twice_is_rejected() {
+        let mut ledger = Ledger::default();
+        ledger.deposit(1, 1, Some(money("2.0"))).unwrap();
+        ledger.dispute(1, 1).unwrap();
+        assert_eq!(ledger.dispute(1, 1), Err(LedgerError::AlreadyDisputed));
+        let account = ledger.account(1).copied().unwrap();
+        assert_eq!(account.available, money("0.0"));
+        assert_eq!(account.held, money("2.0"));
+    }
+
+    #[test]
+    fn disputing_on_a_frozen_account_is_rejected() {
+        let mut ledger = Ledger::default();
+        ledger.deposit(1, 1, Some(money("1.0"))).unwrap();
+        ledger.deposit(1, 2, Some(money("1.0"))).unwrap();
+        ledger.dispute(1, 1).unwrap();
+        ledger.chargeback(1, 1).unwrap();
+        assert_eq!(ledger.dispute(1, 2), Err(LedgerError::FrozenAccount));
+        let account = ledger.account(1).copied().unwrap();
+        assert_eq!(account.available, money("1.0"));
+        assert_eq!(account.held, money("0.0"));
+    }
+
+    #[test]
+    fn deposit_overflowing_the_balance_is_rejected() {
+        let mut ledger = Ledger::default();
+        ledger
+            .deposit(1, 1, Some(money("900000000000000.0")))
+            .unwrap();
+        assert_eq!(
+            ledger.deposit(1, 2, Some(money("900000000000000.0"))),
+            Err(LedgerError::AmountOverflow)
+        );
+        let account = ledger.account(1).copied().unwrap();
+        assert_eq!(account.available, money("900000000000000.0"));
+    }
+}
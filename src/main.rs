@@ -1,12 +1,40 @@
-use linked_hash_map::LinkedHashMap;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use std::{
-    collections::HashMap,
+    fmt,
     fs::File,
     io::{self, BufReader},
-    u16,
 };
 
+mod ledger;
+mod money;
+mod server;
+
+use ledger::{Ledger, LedgerError};
+use money::Money;
+
+/// A transaction record was rejected, either because it couldn't be
+/// parsed at all or because the ledger refused to apply it.
+#[derive(Debug)]
+enum Diagnostic {
+    Malformed(String),
+    Rejected {
+        client: ClientId,
+        tx: TxId,
+        reason: LedgerError,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::Malformed(message) => write!(f, "malformed record: {}", message),
+            Diagnostic::Rejected { client, tx, reason } => {
+                write!(f, "client {} tx {}: {}", client, tx, reason)
+            }
+        }
+    }
+}
+
 type ClientId = u16;
 type TxId = u16;
 
@@ -16,72 +44,47 @@ struct Transaction {
     r#type: String,
     client: ClientId,
     tx: TxId,
-    amount: Option<f64>,
-    #[serde(skip)]
-    disputed: bool, // is on dispute?
-    #[serde(skip)]
-    chargeback: bool, // is chargeback requested?
+    amount: Option<Money>,
 }
 
 // Acount implementation.
 // This struct shows the state at a certain point in time.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Copy)]
 struct Account {
-    pub available: f64,
-    pub held: f64,
+    pub available: Money,
+    pub held: Money,
     pub locked: bool,
 }
 
 impl Account {
-    pub fn total(&self) -> f64 {
+    pub fn total(&self) -> Money {
         self.available + self.held
     }
 }
 
-// Client implementation
-#[derive(Debug)]
-struct Client {
-    id: ClientId,                         // Client id
-    tx: LinkedHashMap<TxId, Transaction>, // List of transactions
-}
-
-impl Client {
-    fn new(id: ClientId) -> Self {
-        Self {
-            id,
-            tx: Default::default(),
-        }
-    }
-
-    pub fn get_account(&self) -> Account {
-        let account = Account::default();
-        let account = self.tx.iter().fold(account, |mut account, (_, tx)| {
-            let amount = tx.amount.unwrap_or(0f64);
-            if account.locked {
-                return account;
-            }
-            match tx.r#type.to_lowercase().as_str() {
-                _ if tx.chargeback => account.locked = true,
-                _ if tx.disputed => account.held += amount,
-                "deposit" => account.available += amount,
-                "withdrawal" => {
-                    if account.available >= amount {
-                        account.available -= amount
-                    }
-                }
-                _ => (),
-            }
-            account
-        });
-        account
+// Serializes the same available/held/total/locked shape as the CSV
+// output, so the HTTP service and the batch CLI agree on account state.
+impl Serialize for Account {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Account", 4)?;
+        state.serialize_field("available", &self.available)?;
+        state.serialize_field("held", &self.held)?;
+        state.serialize_field("total", &self.total())?;
+        state.serialize_field("locked", &self.locked)?;
+        state.end()
     }
 }
 
 // TransactionProcessor reads and creates the
-// final state of the Client and Account data
+// final state of the Account data
 #[derive(Debug, Default)]
 struct TransactionProcessor {
-    clients: HashMap<ClientId, Client>,
+    ledger: Ledger,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl TransactionProcessor {
@@ -89,56 +92,68 @@ impl TransactionProcessor {
         Self::default()
     }
 
-    // Reads the transaction list and creates a client list with associated
-    // Account data
+    // Reads the transaction list and applies each record to the ledger
+    // as it is read, incrementally updating account balances. Records
+    // that fail to parse or that the ledger rejects are recorded in
+    // `diagnostics` instead of being silently dropped.
     pub fn read_transactions(&mut self, reader: Box<dyn io::Read>) {
         let mut reader = csv::ReaderBuilder::new()
             .trim(csv::Trim::All)
+            .flexible(true)
             .from_reader(reader);
-        self.clients = reader.deserialize().filter_map(|x| x.ok()).fold(
-            HashMap::new(),
-            |mut clients, tx: Transaction| {
-                let client = clients
-                    .entry(tx.client)
-                    .or_insert_with(|| Client::new(tx.client));
-                match tx.r#type.to_lowercase().as_str() {
-                    "dispute" => {
-                        if let Some(tx) = client.tx.get_mut(&tx.tx) {
-                            tx.disputed = true;
-                        }
-                    }
-                    "resolve" => {
-                        if let Some(tx) = client.tx.get_mut(&tx.tx) {
-                            tx.disputed = false;
-                        }
-                    }
-                    "chargeback" => {
-                        if let Some(tx) = client.tx.get_mut(&tx.tx) {
-                            if tx.disputed {
-                                tx.chargeback = true;
-                            }
-                        }
-                    }
-                    _ => {
-                        client.tx.insert(tx.tx, tx);
-                    }
-                }
-                clients
-            },
-        );
+        let headers = reader.headers().cloned();
+        let headers = match headers {
+            Ok(headers) => headers,
+            Err(err) => {
+                self.diagnostics.push(Diagnostic::Malformed(err.to_string()));
+                return;
+            }
+        };
+        for result in reader.records() {
+            match result {
+                // A line that's blank once every field is trimmed (e.g. stray
+                // trailing whitespace) isn't a malformed record, just noise.
+                Ok(record) if record.iter().all(str::is_empty) => {}
+                Ok(record) => match record.deserialize::<Transaction>(Some(&headers)) {
+                    Ok(tx) => self.apply(tx),
+                    Err(err) => self.diagnostics.push(Diagnostic::Malformed(err.to_string())),
+                },
+                Err(err) => self.diagnostics.push(Diagnostic::Malformed(err.to_string())),
+            }
+        }
+    }
+
+    pub(crate) fn apply(&mut self, tx: Transaction) {
+        let client = tx.client;
+        let id = tx.tx;
+        let result = match tx.r#type.to_lowercase().as_str() {
+            "deposit" => self.ledger.deposit(client, id, tx.amount),
+            "withdrawal" => self.ledger.withdraw(client, id, tx.amount),
+            "dispute" => self.ledger.dispute(client, id),
+            "resolve" => self.ledger.resolve(client, id),
+            "chargeback" => self.ledger.chargeback(client, id),
+            _ => Ok(()),
+        };
+        if let Err(reason) = result {
+            self.diagnostics.push(Diagnostic::Rejected { client, tx: id, reason });
+        }
+    }
+
+    // diagnostics accumulated while reading, in the order they occurred
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
     }
 
     // returns the status
     pub fn get_status(&self) -> String {
         let mut status = "client,available,held,total\n".to_string();
         let lines = self
-            .clients
-            .iter()
-            .map(|(_, client)| {
-                let account = client.get_account();
+            .ledger
+            .accounts()
+            .map(|(id, account)| {
                 format!(
                     "{},{},{},{},{}",
-                    client.id,
+                    id,
                     account.available,
                     account.held,
                     account.total(),
@@ -151,9 +166,8 @@ impl TransactionProcessor {
         status
     }
 
-    #[cfg(test)]
-    pub fn get_clients(&self) -> Vec<&Client> {
-        self.clients.values().collect()
+    pub(crate) fn get_account(&self, client: ClientId) -> Account {
+        self.ledger.account(client).copied().unwrap_or_default()
     }
 }
 
@@ -162,26 +176,42 @@ fn get_usage(app: &str) -> String {
         r#"
 Error: Missing input file
 
-usage: {} FILE"#,
+usage: {0} FILE
+       {0} --serve ADDR"#,
         app
     )
 }
+
 fn main() {
     let mut args = std::env::args();
     let usage = get_usage(&args.next().unwrap());
-    let inpu_file = args.next().expect(&usage);
-    let input_file =
-        File::open(&inpu_file).unwrap_or_else(|_| panic!("Failed to open file {}", inpu_file));
-    let reader = BufReader::new(input_file);
-    let mut tp = TransactionProcessor::new();
-    tp.read_transactions(Box::new(reader));
-    println!("{}", tp.get_status());
+    match args.next().unwrap_or_else(|| panic!("{}", usage)) {
+        flag if flag == "--serve" => {
+            let addr = args.next().unwrap_or_else(|| panic!("{}", usage));
+            server::serve(&addr).unwrap_or_else(|e| panic!("Failed to start server: {}", e));
+        }
+        inpu_file => {
+            let input_file = File::open(&inpu_file)
+                .unwrap_or_else(|_| panic!("Failed to open file {}", inpu_file));
+            let reader = BufReader::new(input_file);
+            let mut tp = TransactionProcessor::new();
+            tp.read_transactions(Box::new(reader));
+            for diagnostic in tp.diagnostics() {
+                eprintln!("{}", diagnostic);
+            }
+            println!("{}", tp.get_status());
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn money(s: &str) -> Money {
+        s.parse().unwrap()
+    }
+
     #[test]
     fn deposit_should_work() {
         let transactions = r#"
@@ -190,9 +220,7 @@ mod tests {
         "#;
         let mut tp = TransactionProcessor::new();
         tp.read_transactions(Box::new(transactions.as_bytes()));
-        let clients = tp.get_clients();
-        assert_eq!(clients.len(), 1);
-        assert_eq!(clients[0].get_account().available, 1.0);
+        assert_eq!(tp.get_account(1).available, money("1.0"));
     }
 
     #[test]
@@ -204,9 +232,7 @@ mod tests {
         "#;
         let mut tp = TransactionProcessor::new();
         tp.read_transactions(Box::new(transactions.as_bytes()));
-        let clients = tp.get_clients();
-        assert_eq!(clients.len(), 1);
-        assert_eq!(clients[0].get_account().available, 0.5);
+        assert_eq!(tp.get_account(1).available, money("0.5"));
     }
 
     #[test]
@@ -218,8 +244,7 @@ mod tests {
         "#;
         let mut tp = TransactionProcessor::new();
         tp.read_transactions(Box::new(transactions.as_bytes()));
-        let clients = tp.get_clients();
-        assert_eq!(clients[0].get_account().available, 1.0);
+        assert_eq!(tp.get_account(1).available, money("1.0"));
     }
 
     #[test]
@@ -231,8 +256,8 @@ mod tests {
         "#;
         let mut tp = TransactionProcessor::new();
         tp.read_transactions(Box::new(transactions.as_bytes()));
-        let clients = tp.get_clients();
-        assert_eq!(clients.len(), 2);
+        assert_eq!(tp.get_account(1).available, money("1.0"));
+        assert_eq!(tp.get_account(2).available, money("1.5"));
     }
 
     #[test]
@@ -244,10 +269,9 @@ mod tests {
         "#;
         let mut tp = TransactionProcessor::new();
         tp.read_transactions(Box::new(transactions.as_bytes()));
-        let clients = tp.get_clients();
-        let account = clients[0].get_account();
-        assert_eq!(account.available, 0.0, "incorrect available balance");
-        assert_eq!(account.held, 1.0, "incorrect held balance");
+        let account = tp.get_account(1);
+        assert_eq!(account.available, money("0.0"), "incorrect available balance");
+        assert_eq!(account.held, money("1.0"), "incorrect held balance");
     }
 
     #[test]
@@ -260,10 +284,9 @@ mod tests {
         "#;
         let mut tp = TransactionProcessor::new();
         tp.read_transactions(Box::new(transactions.as_bytes()));
-        let clients = tp.get_clients();
-        let account = clients[0].get_account();
-        assert_eq!(account.available, 1.0, "incorrect available balance");
-        assert_eq!(account.held, 0.0, "incorrect held balance");
+        let account = tp.get_account(1);
+        assert_eq!(account.available, money("1.0"), "incorrect available balance");
+        assert_eq!(account.held, money("0.0"), "incorrect held balance");
     }
 
     #[test]
@@ -276,11 +299,10 @@ mod tests {
         "#;
         let mut tp = TransactionProcessor::new();
         tp.read_transactions(Box::new(transactions.as_bytes()));
-        let clients = tp.get_clients();
-        let account = clients[0].get_account();
-        assert_eq!(account.available, 0.0, "unexpected available balance");
-        assert_eq!(account.held, 0.0, "unexpected held balance");
-        assert_eq!(account.locked, true, "unexpected account state");
+        let account = tp.get_account(1);
+        assert_eq!(account.available, money("0.0"), "unexpected available balance");
+        assert_eq!(account.held, money("0.0"), "unexpected held balance");
+        assert!(account.locked, "unexpected account state");
     }
 
     #[test]
@@ -294,11 +316,10 @@ mod tests {
         "#;
         let mut tp = TransactionProcessor::new();
         tp.read_transactions(Box::new(transactions.as_bytes()));
-        let clients = tp.get_clients();
-        let account = clients[0].get_account();
-        assert_eq!(account.available, 1.0, "unexpected available balance");
-        assert_eq!(account.held, 0.0, "unexpected held balance");
-        assert_eq!(account.locked, false, "unexpected account state");
+        let account = tp.get_account(1);
+        assert_eq!(account.available, money("1.0"), "unexpected available balance");
+        assert_eq!(account.held, money("0.0"), "unexpected held balance");
+        assert!(!account.locked, "unexpected account state");
     }
 
     #[test]
@@ -318,8 +339,8 @@ mod tests {
         tp.read_transactions(Box::new(transactions.as_bytes()));
         let output = tp.get_status();
         let expected = r#"client,available,held,total
-        1,1.5,0,1.5,false
-        2,0,0,0,true"#;
+        1,1.5000,0.0000,1.5000,false
+        2,0.0000,0.0000,0.0000,true"#;
         assert_eq!(
             trim_lines(expected),
             trim_lines(&output),
@@ -327,6 +348,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn overwithdrawal_is_reported_as_diagnostic() {
+        let transactions = r#"
+        type,client,tx,amount
+        deposit, 1, 1, 1.0
+        withdrawal, 1, 2, 1.5"#;
+        let mut tp = TransactionProcessor::new();
+        tp.read_transactions(Box::new(transactions.as_bytes()));
+        assert!(matches!(
+            tp.diagnostics(),
+            [Diagnostic::Rejected {
+                reason: LedgerError::NotEnoughFunds,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn dispute_of_unknown_tx_is_reported_as_diagnostic() {
+        let transactions = r#"
+        type,client,tx,amount
+        deposit, 1, 1, 1.0
+        dispute, 1, 99,"#;
+        let mut tp = TransactionProcessor::new();
+        tp.read_transactions(Box::new(transactions.as_bytes()));
+        assert!(matches!(
+            tp.diagnostics(),
+            [Diagnostic::Rejected {
+                reason: LedgerError::UnknownTx,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn a_whitespace_only_line_is_not_reported_as_a_diagnostic() {
+        let transactions = "type,client,tx,amount\ndeposit, 1, 1, 1.0\n   \n";
+        let mut tp = TransactionProcessor::new();
+        tp.read_transactions(Box::new(transactions.as_bytes()));
+        assert!(tp.diagnostics().is_empty());
+        assert_eq!(tp.get_account(1).available, money("1.0"));
+    }
+
     fn trim_lines(str: &str) -> String {
         str.lines()
             .map(|line| line.trim().into())
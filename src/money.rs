@@ -0,0 +1,170 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Scale factor between a `Money` unit and its underlying `i64`: four
+/// decimal places, enough to hold amounts such as `2.742` exactly.
+const SCALE: i64 = 10_000;
+
+/// Fixed-point money amount, stored as ten-thousandths of a unit.
+///
+/// `f64` accumulates binary-float error across repeated add/subtract
+/// operations and prints values like `1.5000000000000002`; this type
+/// keeps all arithmetic exact and formatting deterministic.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Money(i64);
+
+impl Money {
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.0.checked_sub(other.0).map(Money)
+    }
+}
+
+impl std::ops::Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        self.checked_add(rhs).expect("money addition overflowed")
+    }
+}
+
+impl std::ops::AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        *self = *self + rhs;
+    }
+}
+
+impl std::ops::Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        self.checked_sub(rhs).expect("money subtraction overflowed")
+    }
+}
+
+impl std::ops::SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        *self = *self - rhs;
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let whole = self.0.abs() / SCALE;
+        let frac = self.0.abs() % SCALE;
+        write!(f, "{}{}.{:04}", sign, whole, frac)
+    }
+}
+
+impl std::str::FromStr for Money {
+    type Err = ParseMoneyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (whole, frac) = match s.split_once('.') {
+            Some((whole, frac)) => (whole, frac),
+            None => (s, ""),
+        };
+        if frac.len() > 4 {
+            return Err(ParseMoneyError::TooManyFractionalDigits);
+        }
+        let whole: i64 = whole.parse().map_err(|_| ParseMoneyError::Invalid)?;
+        let frac_digits: i64 = if frac.is_empty() {
+            0
+        } else {
+            format!("{:0<4}", frac)
+                .parse()
+                .map_err(|_| ParseMoneyError::Invalid)?
+        };
+        let whole_magnitude = whole.checked_abs().ok_or(ParseMoneyError::Overflow)?;
+        let magnitude = whole_magnitude
+            .checked_mul(SCALE)
+            .and_then(|scaled| scaled.checked_add(frac_digits))
+            .ok_or(ParseMoneyError::Overflow)?;
+        Ok(Money(if whole < 0 { -magnitude } else { magnitude }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMoneyError {
+    Invalid,
+    TooManyFractionalDigits,
+    Overflow,
+}
+
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMoneyError::Invalid => write!(f, "invalid money amount"),
+            ParseMoneyError::TooManyFractionalDigits => {
+                write!(f, "amount has more than four fractional digits")
+            }
+            ParseMoneyError::Overflow => write!(f, "amount is too large to represent"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+// Serialized as a string (e.g. `"1.5000"`) rather than a JSON number, so
+// consumers don't reintroduce the float-precision problem this type
+// exists to avoid.
+impl Serialize for Money {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_with_four_decimal_places() {
+        assert_eq!("1.5000".parse::<Money>().unwrap().to_string(), "1.5000");
+        assert_eq!("2.742".parse::<Money>().unwrap().to_string(), "2.7420");
+        assert_eq!("5".parse::<Money>().unwrap().to_string(), "5.0000");
+    }
+
+    #[test]
+    fn rejects_more_than_four_fractional_digits() {
+        assert_eq!(
+            "1.23456".parse::<Money>(),
+            Err(ParseMoneyError::TooManyFractionalDigits)
+        );
+    }
+
+    #[test]
+    fn arithmetic_is_exact() {
+        let a = "0.1".parse::<Money>().unwrap();
+        let b = "0.2".parse::<Money>().unwrap();
+        assert_eq!(a.checked_add(b).unwrap().to_string(), "0.3000");
+    }
+
+    #[test]
+    fn rejects_amounts_too_large_to_represent() {
+        assert_eq!(
+            "999999999999999999.0".parse::<Money>(),
+            Err(ParseMoneyError::Overflow)
+        );
+        assert_eq!(
+            "-9223372036854775808.0".parse::<Money>(),
+            Err(ParseMoneyError::Overflow)
+        );
+    }
+}
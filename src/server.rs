@@ -0,0 +1,196 @@
+use crate::{ClientId, Transaction, TransactionProcessor};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tiny_http::{Method, Response, Server};
+
+/// Number of worker threads pulling requests off the shared `Server`.
+/// Several threads competing for the single `Mutex<TransactionProcessor>`
+/// is what actually gives concurrent posts graceful handling -- one
+/// thread alone would process requests strictly sequentially and the
+/// lock would never arbitrate anything.
+const WORKERS: usize = 4;
+
+/// Runs the HTTP service mode: `POST /transactions` ingests a single
+/// transaction or a JSON array of transactions, `GET /accounts/{client}`
+/// returns that client's current `available`/`held`/`total`/`locked`.
+/// This reuses the same `TransactionProcessor` the one-shot `FILE` mode
+/// uses, kept alive across requests behind a single lock so the account
+/// map survives between posts -- a single lock around the whole
+/// processor is enough since requests are small and infrequent compared
+/// to a batch file load.
+pub fn serve(addr: &str) -> Result<(), String> {
+    let server = Arc::new(Server::http(addr).map_err(|e| e.to_string())?);
+    let processor = Arc::new(Mutex::new(TransactionProcessor::new()));
+
+    let workers: Vec<_> = (0..WORKERS)
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let processor = Arc::clone(&processor);
+            thread::spawn(move || {
+                while let Ok(mut request) = server.recv() {
+                    let response = handle(&processor, &mut request);
+                    let _ = request.respond(response);
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+fn handle(
+    processor: &Mutex<TransactionProcessor>,
+    request: &mut tiny_http::Request,
+) -> Response<Cursor<Vec<u8>>> {
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let (status, body) = match (method, url.as_str()) {
+        (Method::Post, "/transactions") => {
+            let mut raw = String::new();
+            match std::io::Read::read_to_string(request.as_reader(), &mut raw) {
+                Ok(_) => post_transactions(processor, &raw),
+                Err(_) => (400, serde_json::json!({ "error": "could not read request body" })),
+            }
+        }
+        (Method::Get, url) if url.starts_with("/accounts/") => {
+            get_account(processor, &url["/accounts/".len()..])
+        }
+        _ => (404, serde_json::json!({ "error": "not found" })),
+    };
+    json_response(status, &body)
+}
+
+/// Applies the transactions in `body` (a single transaction or a JSON
+/// array of them) to the shared processor, returning the HTTP status and
+/// JSON body to send back.
+fn post_transactions(processor: &Mutex<TransactionProcessor>, body: &str) -> (u16, serde_json::Value) {
+    let transactions = match parse_transactions(body) {
+        Ok(transactions) => transactions,
+        Err(_) => {
+            return (
+                400,
+                serde_json::json!({ "error": "body is not a transaction or a batch of them" }),
+            )
+        }
+    };
+
+    let mut processor = processor.lock().unwrap_or_else(|e| e.into_inner());
+    for tx in transactions {
+        processor.apply(tx);
+    }
+    (202, serde_json::json!({ "status": "accepted" }))
+}
+
+fn parse_transactions(body: &str) -> serde_json::Result<Vec<Transaction>> {
+    serde_json::from_str::<Vec<Transaction>>(body)
+        .or_else(|_| serde_json::from_str::<Transaction>(body).map(|tx| vec![tx]))
+}
+
+fn get_account(processor: &Mutex<TransactionProcessor>, client: &str) -> (u16, serde_json::Value) {
+    let client: ClientId = match client.parse() {
+        Ok(client) => client,
+        Err(_) => return (400, serde_json::json!({ "error": "invalid client id" })),
+    };
+    let account = processor
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_account(client);
+    (200, serde_json::to_value(account).unwrap())
+}
+
+fn json_response(status: u16, body: &serde_json::Value) -> Response<Cursor<Vec<u8>>> {
+    let payload = serde_json::to_vec(body).unwrap_or_default();
+    let header = tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("static header is valid");
+    Response::from_data(payload)
+        .with_status_code(status)
+        .with_header(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_single_transaction() {
+        let body = r#"{"type":"deposit","client":1,"tx":1,"amount":"1.0"}"#;
+        let transactions = parse_transactions(body).unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].client, 1);
+    }
+
+    #[test]
+    fn parses_a_batch_of_transactions() {
+        let body = r#"[
+            {"type":"deposit","client":1,"tx":1,"amount":"1.0"},
+            {"type":"deposit","client":2,"tx":2,"amount":"2.0"}
+        ]"#;
+        let transactions = parse_transactions(body).unwrap();
+        assert_eq!(transactions.len(), 2);
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        assert!(parse_transactions("not json").is_err());
+    }
+
+    #[test]
+    fn post_transactions_applies_a_batch_and_accepts() {
+        let processor = Mutex::new(TransactionProcessor::new());
+        let body = r#"[{"type":"deposit","client":1,"tx":1,"amount":"1.0"}]"#;
+        let (status, json) = post_transactions(&processor, body);
+        assert_eq!(status, 202);
+        assert_eq!(json["status"], "accepted");
+        assert_eq!(
+            processor.lock().unwrap().get_account(1).available,
+            "1.0".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn post_transactions_rejects_malformed_body() {
+        let processor = Mutex::new(TransactionProcessor::new());
+        let (status, _) = post_transactions(&processor, "not json");
+        assert_eq!(status, 400);
+    }
+
+    #[test]
+    fn get_account_rejects_non_numeric_client_id() {
+        let processor = Mutex::new(TransactionProcessor::new());
+        let (status, _) = get_account(&processor, "not-a-client");
+        assert_eq!(status, 400);
+    }
+
+    #[test]
+    fn get_account_returns_default_account_for_unknown_client() {
+        let processor = Mutex::new(TransactionProcessor::new());
+        let (status, json) = get_account(&processor, "42");
+        assert_eq!(status, 200);
+        assert_eq!(json["locked"], false);
+    }
+
+    #[test]
+    fn requests_recover_from_a_poisoned_lock() {
+        let processor = Mutex::new(TransactionProcessor::new());
+        let _ = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _guard = processor.lock().unwrap();
+                    panic!("simulated panic while holding the lock");
+                })
+                .join()
+        });
+        assert!(processor.is_poisoned());
+
+        let body = r#"{"type":"deposit","client":1,"tx":1,"amount":"1.0"}"#;
+        let (status, _) = post_transactions(&processor, body);
+        assert_eq!(status, 202);
+
+        let (status, _) = get_account(&processor, "1");
+        assert_eq!(status, 200);
+    }
+}